@@ -0,0 +1,201 @@
+use std::sync::Arc;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use async_trait::async_trait;
+use axum::extract::FromRequestParts;
+use axum::http::header::AUTHORIZATION;
+use axum::http::request::Parts;
+use jsonwebtoken::{decode, encode, DecodingKey, EncodingKey, Header, Validation};
+use serde::{Deserialize, Serialize};
+
+use crate::config::Config;
+use crate::error::AppError;
+use crate::hash::HashScheme;
+
+/// How long an issued token remains valid for.
+const TOKEN_TTL_SECONDS: u64 = 60 * 60 * 24 * 7;
+
+#[derive(Debug, Serialize, Deserialize)]
+struct Claims {
+    sub: String,
+    exp: u64,
+}
+
+/// Hash a player's account password the same way a game's password is hashed, namespaced by
+/// username so two players choosing the same password don't end up with the same stored hash.
+pub fn hash_password(password: &str, username: &str, bcrypt_cost: u32) -> String {
+    HashScheme::Bcrypt { cost: bcrypt_cost }.hash(password, username)
+}
+
+/// Compare two password hashes without early-exiting on the first differing byte, so a login
+/// attempt can't be timed to learn how much of the stored hash it got right.
+pub fn verify_password_hash(stored: &str, candidate: &str) -> bool {
+    let (stored, candidate) = (stored.as_bytes(), candidate.as_bytes());
+    if stored.len() != candidate.len() {
+        return false;
+    }
+    stored
+        .iter()
+        .zip(candidate.iter())
+        .fold(0u8, |diff, (a, b)| diff | (a ^ b))
+        == 0
+}
+
+/// Issue a signed, time-limited token proving a request comes from `player`.
+pub fn issue_token(player: &str, secret: &[u8]) -> Result<String, AppError> {
+    let claims = Claims {
+        sub: player.to_owned(),
+        exp: unix_timestamp_now() + TOKEN_TTL_SECONDS,
+    };
+    encode(
+        &Header::default(),
+        &claims,
+        &EncodingKey::from_secret(secret),
+    )
+    .map_err(|_| AppError::InternalServerError)
+}
+
+fn verify_token(token: &str, secret: &[u8]) -> Result<String, AppError> {
+    let data = decode::<Claims>(
+        token,
+        &DecodingKey::from_secret(secret),
+        &Validation::default(),
+    )
+    .map_err(|_| AppError::Unauthorized)?;
+    Ok(data.claims.sub)
+}
+
+fn unix_timestamp_now() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .expect("system clock is after the Unix epoch")
+        .as_secs()
+}
+
+/// Extractor for a handler that requires a valid `Authorization: Bearer <token>` header.
+///
+/// Wrap in `Option<AuthenticatedPlayer>` for handlers where authentication is optional (e.g.
+/// creating an anonymous game) rather than required.
+pub struct AuthenticatedPlayer(pub String);
+
+#[async_trait]
+impl<S> FromRequestParts<S> for AuthenticatedPlayer
+where
+    S: Send + Sync,
+{
+    type Rejection = AppError;
+
+    async fn from_request_parts(parts: &mut Parts, _state: &S) -> Result<Self, Self::Rejection> {
+        let config = parts
+            .extensions
+            .get::<Arc<Config>>()
+            .ok_or(AppError::InternalServerError)?;
+        let token = parts
+            .headers
+            .get(AUTHORIZATION)
+            .and_then(|value| value.to_str().ok())
+            .and_then(|value| value.strip_prefix("Bearer "))
+            .ok_or(AppError::Unauthorized)?;
+        let player = verify_token(token, config.jwt_secret.as_bytes())?;
+        Ok(AuthenticatedPlayer(player))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use axum::http::Request;
+
+    fn config() -> Arc<Config> {
+        Arc::new(Config {
+            redis_url: "redis://localhost".into(),
+            bind_url: "0.0.0.0:0".into(),
+            bcrypt_cost: 4,
+            jwt_secret: "test-secret".into(),
+        })
+    }
+
+    #[test]
+    fn verify_password_hash_accepts_matching_hashes() {
+        assert!(verify_password_hash("abc123", "abc123"));
+    }
+
+    #[test]
+    fn verify_password_hash_rejects_mismatched_hashes() {
+        assert!(!verify_password_hash("abc123", "abc124"));
+    }
+
+    #[test]
+    fn verify_password_hash_rejects_different_length_hashes() {
+        assert!(!verify_password_hash("abc123", "abc1234"));
+    }
+
+    #[test]
+    fn issue_token_roundtrips_through_verify_token() {
+        let secret = b"roundtrip-secret";
+        let token = issue_token("alice", secret).unwrap();
+        assert_eq!(verify_token(&token, secret).unwrap(), "alice");
+    }
+
+    #[test]
+    fn verify_token_rejects_a_token_signed_with_a_different_secret() {
+        let token = issue_token("alice", b"correct-secret").unwrap();
+        let result = verify_token(&token, b"wrong-secret");
+        assert!(matches!(result, Err(AppError::Unauthorized)));
+    }
+
+    #[test]
+    fn verify_token_rejects_an_expired_token() {
+        let secret = b"expiry-secret";
+        let claims = Claims {
+            sub: "alice".into(),
+            exp: 0,
+        };
+        let token =
+            encode(&Header::default(), &claims, &EncodingKey::from_secret(secret)).unwrap();
+        let result = verify_token(&token, secret);
+        assert!(matches!(result, Err(AppError::Unauthorized)));
+    }
+
+    #[test]
+    fn verify_token_rejects_a_malformed_token() {
+        let result = verify_token("not-a-jwt", b"secret");
+        assert!(matches!(result, Err(AppError::Unauthorized)));
+    }
+
+    #[tokio::test]
+    async fn extractor_rejects_a_request_missing_the_authorization_header() {
+        let (mut parts, _) = Request::builder().body(()).unwrap().into_parts();
+        parts.extensions.insert(config());
+        let result = AuthenticatedPlayer::from_request_parts(&mut parts, &()).await;
+        assert!(matches!(result, Err(AppError::Unauthorized)));
+    }
+
+    #[tokio::test]
+    async fn extractor_rejects_a_malformed_authorization_header() {
+        let (mut parts, _) = Request::builder()
+            .header(AUTHORIZATION, "not-bearer-scheme")
+            .body(())
+            .unwrap()
+            .into_parts();
+        parts.extensions.insert(config());
+        let result = AuthenticatedPlayer::from_request_parts(&mut parts, &()).await;
+        assert!(matches!(result, Err(AppError::Unauthorized)));
+    }
+
+    #[tokio::test]
+    async fn extractor_accepts_a_valid_bearer_token() {
+        let cfg = config();
+        let token = issue_token("alice", cfg.jwt_secret.as_bytes()).unwrap();
+        let (mut parts, _) = Request::builder()
+            .header(AUTHORIZATION, format!("Bearer {}", token))
+            .body(())
+            .unwrap()
+            .into_parts();
+        parts.extensions.insert(cfg);
+        let AuthenticatedPlayer(player) = AuthenticatedPlayer::from_request_parts(&mut parts, &())
+            .await
+            .unwrap();
+        assert_eq!(player, "alice");
+    }
+}
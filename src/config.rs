@@ -0,0 +1,21 @@
+use clap::Parser;
+
+#[derive(Parser, Debug)]
+pub struct Config {
+    /// URL of the Redis instance used to store game state.
+    #[clap(long, env)]
+    pub redis_url: String,
+
+    /// Address the HTTP server binds to.
+    #[clap(long, env)]
+    pub bind_url: String,
+
+    /// Cost (number of `bcrypt_pbkdf` rounds) used when a game requests the `bcrypt` hash
+    /// scheme. Higher values make brute-forcing a game's password more expensive.
+    #[clap(long, env, default_value_t = 10)]
+    pub bcrypt_cost: u32,
+
+    /// Secret used to sign and verify player JWTs. Rotating it invalidates every issued token.
+    #[clap(long, env)]
+    pub jwt_secret: String,
+}
@@ -7,6 +7,8 @@ pub enum AppError {
     GameNotFound,
     InternalServerError,
     BadRequest,
+    Unauthorized,
+    RoomFinished,
 }
 
 impl IntoResponse for AppError {
@@ -17,6 +19,8 @@ impl IntoResponse for AppError {
                 (StatusCode::INTERNAL_SERVER_ERROR, "Internal server error")
             }
             AppError::BadRequest => (StatusCode::BAD_REQUEST, "Bad request"),
+            AppError::Unauthorized => (StatusCode::UNAUTHORIZED, "Unauthorized"),
+            AppError::RoomFinished => (StatusCode::CONFLICT, "Room already finished"),
         };
 
         let body = Json(json!({
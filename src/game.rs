@@ -1,16 +1,17 @@
 use std::sync::Arc;
+use std::time::{SystemTime, UNIX_EPOCH};
 
-use base64::encode;
-use md5::{Digest, Md5};
 use rand::{Rng, SeedableRng};
-use redis::Client as RedisClient;
 use serde::{Deserialize, Serialize};
 use uuid::Uuid;
 
 use crate::error::AppError;
+use crate::hash::HashScheme;
+use crate::leaderboard::LeaderboardStore;
+use crate::storage::GameStore;
 
 const MAX_GUESS: usize = 64;
-const PASSWORD_LENGTH: usize = 8;
+pub(crate) const PASSWORD_LENGTH: usize = 8;
 const SALT_LENGTH: usize = 8;
 const GAME_EXPIRE: usize = 60 * 60 * 24;
 
@@ -25,20 +26,12 @@ pub struct GameCreationInfo {
     pub salt: String,
     pub guess_count: usize,
     pub id: Uuid,
+    pub scheme: HashScheme,
 }
 
-pub async fn get_game_info(redis: Arc<RedisClient>, game_id: Uuid) -> Result<GameInfo, AppError> {
-    let mut conn = redis
-        .get_async_connection()
-        .await
-        .map_err(|_| AppError::InternalServerError)?;
-    let (guess_count, salt): (Option<usize>, Option<String>) = redis::pipe()
-        .get(format!("game:{}:guess_count", game_id))
-        .get(format!("game:{}:salt", game_id))
-        .query_async(&mut conn)
-        .await
-        .map_err(|_| AppError::InternalServerError)?;
-    if let (Some(guess_count), Some(salt)) = (guess_count, salt) {
+pub async fn get_game_info(store: Arc<dyn GameStore>, game_id: Uuid) -> Result<GameInfo, AppError> {
+    let loaded = store.load(game_id).await?;
+    if let (Some(guess_count), Some(salt)) = (loaded.guess_count, loaded.salt) {
         Ok(GameInfo { salt, guess_count })
     } else {
         Err(AppError::GameNotFound)
@@ -50,9 +43,17 @@ pub struct GuessResult {
     pub hash: String,
     pub guess: Vec<Match>,
     pub key: Option<String>,
+    /// Present only on a win when the caller supplied a `player` name: their 0-based rank on
+    /// the leaderboard (0 = best) and how long the game took to solve.
+    pub rank: Option<u64>,
+    pub elapsed_seconds: Option<u64>,
 }
 
-pub async fn create_game(redis: Arc<RedisClient>) -> Result<GameCreationInfo, AppError> {
+pub async fn create_game(
+    store: Arc<dyn GameStore>,
+    scheme: HashScheme,
+    owner: Option<String>,
+) -> Result<GameCreationInfo, AppError> {
     let mut rng = rand::rngs::StdRng::from_entropy();
     let salt: String = String::from_utf8(
         (0..SALT_LENGTH)
@@ -66,77 +67,86 @@ pub async fn create_game(redis: Arc<RedisClient>) -> Result<GameCreationInfo, Ap
             .collect(),
     )
     .unwrap();
-    let mut hasher = Md5::new();
-    hasher.update(password.as_bytes());
-    hasher.update(salt.as_bytes());
-    let password = encode(hasher.finalize());
+    let password = scheme.hash(&password, &salt);
     let uuid = Uuid::from_bytes(rng.gen());
 
-    let mut conn = redis
-        .get_async_connection()
-        .await
-        .map_err(|_| AppError::InternalServerError)?;
-
-    redis::pipe()
-        .set_ex(format!("game:{}:guess_count", uuid), 0usize, GAME_EXPIRE)
-        .set_ex(format!("game:{}:salt", uuid), &salt, GAME_EXPIRE)
-        .set_ex(format!("game:{}:password", uuid), &password, GAME_EXPIRE)
-        .query_async(&mut conn)
-        .await
-        .map_err(|_| AppError::InternalServerError)?;
+    store
+        .create(
+            uuid,
+            &salt,
+            &password,
+            scheme,
+            owner.as_deref(),
+            GAME_EXPIRE,
+        )
+        .await?;
 
     Ok(GameCreationInfo {
         salt,
         guess_count: 0,
         id: uuid,
+        scheme,
     })
 }
 
 pub async fn make_guess(
-    redis: Arc<RedisClient>,
+    store: Arc<dyn GameStore>,
+    leaderboard: Arc<dyn LeaderboardStore>,
     game_id: Uuid,
     guess: String,
+    player: Option<String>,
+    requester: Option<String>,
 ) -> Result<GuessResult, AppError> {
     if guess.len() != PASSWORD_LENGTH {
         return Err(AppError::BadRequest);
     }
-    let mut conn = redis
-        .get_async_connection()
-        .await
-        .map_err(|_| AppError::InternalServerError)?;
-    let (guess_count, salt, password): (Option<usize>, Option<String>, Option<String>) =
-        redis::pipe()
-            .incr(format!("game:{}:guess_count", game_id), 1)
-            .get(format!("game:{}:salt", game_id))
-            .get(format!("game:{}:password", game_id))
-            .query_async(&mut conn)
-            .await
-            .map_err(|_| AppError::InternalServerError)?;
-    if let (Some(guess_count), Some(salt), Some(password)) = (guess_count, salt, password) {
+    let loaded = store.load(game_id).await?;
+    if let Some(owner) = &loaded.owner {
+        if requester.as_deref() != Some(owner.as_str()) {
+            return Err(AppError::Unauthorized);
+        }
+    }
+    let guess_count = store.incr_guess_count(game_id).await?;
+    let scheme = loaded.scheme.unwrap_or_default();
+    if let (Some(guess_count), Some(salt), Some(password)) =
+        (guess_count, loaded.salt, loaded.password)
+    {
         if guess_count > MAX_GUESS {
-            redis::pipe()
-                .del(format!("game:{}:guess_count", game_id))
-                .del(format!("game:{}:salt", game_id))
-                .del(format!("game:{}:password", game_id))
-                .query_async(&mut conn)
-                .await
-                .map_err(|_| AppError::InternalServerError)?;
+            store.delete(game_id).await?;
             Err(AppError::GameNotFound)
         } else {
-            let mut hasher = Md5::new();
-            hasher.update(guess);
-            hasher.update(salt);
-            let guess = encode(hasher.finalize());
+            let guess = scheme.hash(&guess, &salt);
             if guess.len() != password.len() {
-                return Err(AppError::BadRequest);
+                return Err(AppError::InternalServerError);
+            }
+            let mut result = check_guess(guess, password);
+            if result.key.is_some() {
+                if let Some(player) = player {
+                    leaderboard.record_solve(&player, guess_count).await?;
+                    result.rank = leaderboard.rank(&player).await?;
+                    result.elapsed_seconds = loaded
+                        .created_at
+                        .map(|created_at| unix_timestamp_now().saturating_sub(created_at));
+                }
+            }
+            // Best-effort: a guess still counts even if nobody is watching the stream right now.
+            if let Ok(event) = serde_json::to_string(&result) {
+                let _ = store.publish(game_id, event).await;
             }
-            Ok(check_guess(guess, password))
+            Ok(result)
         }
     } else {
         Err(AppError::GameNotFound)
     }
 }
 
+fn unix_timestamp_now() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .expect("system clock is after the Unix epoch")
+        .as_secs()
+}
+
 #[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, PartialOrd, Ord, Serialize, Deserialize)]
 /// Represents a match for a given letter against the solution
 pub enum Match {
@@ -148,7 +158,9 @@ pub enum Match {
     Wrong,
 }
 
-fn check_guess(input: String, solution: String) -> GuessResult {
+/// Diff a single guess against the solution. Shared with [`crate::room`] since rooms race several
+/// players against the same password using identical match rules.
+pub(crate) fn check_guess(input: String, solution: String) -> GuessResult {
     assert_eq!(input.len(), solution.len());
 
     let input_str = input;
@@ -186,5 +198,236 @@ fn check_guess(input: String, solution: String) -> GuessResult {
         hash: input_str,
         guess: diff,
         key,
+        rank: None,
+        elapsed_seconds: None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::leaderboard::InMemoryLeaderboardStore;
+    use crate::storage::InMemoryGameStore;
+
+    async fn seed(
+        store: &dyn GameStore,
+        game_id: Uuid,
+        salt: &str,
+        password: &str,
+        scheme: HashScheme,
+    ) {
+        store
+            .create(
+                game_id,
+                salt,
+                &scheme.hash(password, salt),
+                scheme,
+                None,
+                GAME_EXPIRE,
+            )
+            .await
+            .unwrap();
+    }
+
+    fn leaderboard() -> Arc<dyn LeaderboardStore> {
+        Arc::new(InMemoryLeaderboardStore::new())
+    }
+
+    #[tokio::test]
+    async fn full_game_flow_create_wrong_guess_win() {
+        let store: Arc<dyn GameStore> = Arc::new(InMemoryGameStore::new());
+        let leaderboard = leaderboard();
+        let game_id = Uuid::new_v4();
+        seed(&*store, game_id, "saltsalt", "swordfsh", HashScheme::Md5).await;
+
+        let wrong = make_guess(
+            store.clone(),
+            leaderboard.clone(),
+            game_id,
+            "xxxxxxxx".into(),
+            Some("alice".into()),
+            None,
+        )
+        .await
+        .unwrap();
+        assert!(wrong.key.is_none());
+        assert!(wrong.guess.iter().all(|m| *m == Match::Wrong));
+
+        let win = make_guess(
+            store.clone(),
+            leaderboard.clone(),
+            game_id,
+            "swordfsh".into(),
+            Some("alice".into()),
+            None,
+        )
+        .await
+        .unwrap();
+        assert!(win.key.is_some());
+        assert!(win.guess.iter().all(|m| *m == Match::Exact));
+        assert_eq!(win.rank, Some(0));
+        assert!(win.elapsed_seconds.is_some());
+
+        let info = get_game_info(store, game_id).await.unwrap();
+        assert_eq!(info.guess_count, 2);
+    }
+
+    #[tokio::test]
+    async fn sha256_scheme_is_hashed_and_matched_consistently() {
+        let store: Arc<dyn GameStore> = Arc::new(InMemoryGameStore::new());
+        let game_id = Uuid::new_v4();
+        seed(&*store, game_id, "saltsalt", "swordfsh", HashScheme::Sha256).await;
+
+        let win = make_guess(store, leaderboard(), game_id, "swordfsh".into(), None, None)
+            .await
+            .unwrap();
+        assert!(win.key.is_some());
+        assert!(win.guess.iter().all(|m| *m == Match::Exact));
+        assert_eq!(win.rank, None);
+    }
+
+    #[tokio::test]
+    async fn guess_count_overflow_evicts_game() {
+        let store: Arc<dyn GameStore> = Arc::new(InMemoryGameStore::new());
+        let leaderboard = leaderboard();
+        let game_id = Uuid::new_v4();
+        seed(&*store, game_id, "saltsalt", "swordfsh", HashScheme::Md5).await;
+
+        for _ in 0..MAX_GUESS {
+            make_guess(
+                store.clone(),
+                leaderboard.clone(),
+                game_id,
+                "xxxxxxxx".into(),
+                None,
+                None,
+            )
+            .await
+            .unwrap();
+        }
+        let result = make_guess(
+            store.clone(),
+            leaderboard.clone(),
+            game_id,
+            "xxxxxxxx".into(),
+            None,
+            None,
+        )
+        .await;
+        assert!(matches!(result, Err(AppError::GameNotFound)));
+
+        let result = get_game_info(store, game_id).await;
+        assert!(matches!(result, Err(AppError::GameNotFound)));
+    }
+
+    #[tokio::test]
+    async fn missing_game_is_not_found() {
+        let store: Arc<dyn GameStore> = Arc::new(InMemoryGameStore::new());
+        let result = get_game_info(store.clone(), Uuid::new_v4()).await;
+        assert!(matches!(result, Err(AppError::GameNotFound)));
+
+        let result = make_guess(
+            store,
+            leaderboard(),
+            Uuid::new_v4(),
+            "xxxxxxxx".into(),
+            None,
+            None,
+        )
+        .await;
+        assert!(matches!(result, Err(AppError::GameNotFound)));
+    }
+
+    #[tokio::test]
+    async fn corrupt_state_missing_password_is_not_found() {
+        let store = InMemoryGameStore::new();
+        let game_id = Uuid::new_v4();
+        store.insert_raw(
+            game_id,
+            Some(0),
+            Some("saltsalt".into()),
+            None,
+            Some(HashScheme::Md5),
+            None,
+            GAME_EXPIRE,
+        );
+        let store: Arc<dyn GameStore> = Arc::new(store);
+
+        let info = get_game_info(store.clone(), game_id).await.unwrap();
+        assert_eq!(info.salt, "saltsalt");
+
+        let result = make_guess(store, leaderboard(), game_id, "xxxxxxxx".into(), None, None).await;
+        assert!(matches!(result, Err(AppError::GameNotFound)));
+    }
+
+    #[tokio::test]
+    async fn corrupt_state_scheme_mismatched_with_stored_password_is_rejected() {
+        let store = InMemoryGameStore::new();
+        let game_id = Uuid::new_v4();
+        // `password` was hashed with Bcrypt, but `scheme` has since expired and defaulted back
+        // to Md5, so a correctly-hashed guess can never match its length.
+        store.insert_raw(
+            game_id,
+            Some(0),
+            Some("saltsalt".into()),
+            Some(HashScheme::Bcrypt { cost: 4 }.hash("swordfsh", "saltsalt")),
+            None,
+            None,
+            GAME_EXPIRE,
+        );
+        let store: Arc<dyn GameStore> = Arc::new(store);
+
+        let result = make_guess(store, leaderboard(), game_id, "xxxxxxxx".into(), None, None).await;
+        assert!(matches!(result, Err(AppError::InternalServerError)));
+    }
+
+    #[tokio::test]
+    async fn private_game_rejects_guesses_from_non_owners() {
+        let store: Arc<dyn GameStore> = Arc::new(InMemoryGameStore::new());
+        let game_id = Uuid::new_v4();
+        store
+            .create(
+                game_id,
+                "saltsalt",
+                &HashScheme::Md5.hash("swordfsh", "saltsalt"),
+                HashScheme::Md5,
+                Some("alice"),
+                GAME_EXPIRE,
+            )
+            .await
+            .unwrap();
+
+        let result = make_guess(
+            store.clone(),
+            leaderboard(),
+            game_id,
+            "xxxxxxxx".into(),
+            None,
+            None,
+        )
+        .await;
+        assert!(matches!(result, Err(AppError::Unauthorized)));
+
+        let result = make_guess(
+            store.clone(),
+            leaderboard(),
+            game_id,
+            "xxxxxxxx".into(),
+            None,
+            Some("bob".into()),
+        )
+        .await;
+        assert!(matches!(result, Err(AppError::Unauthorized)));
+
+        let result = make_guess(
+            store,
+            leaderboard(),
+            game_id,
+            "xxxxxxxx".into(),
+            None,
+            Some("alice".into()),
+        )
+        .await;
+        assert!(result.is_ok());
     }
 }
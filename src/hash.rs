@@ -0,0 +1,67 @@
+use base64::encode;
+use md5::Md5;
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+
+/// Algorithm (and cost, where applicable) used to hash a game's password.
+///
+/// Chosen once at game creation time and stored alongside the salt, so `make_guess` can hash
+/// incoming guesses the same way without having to guess which scheme produced the stored value.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum HashScheme {
+    Md5,
+    Sha256,
+    /// Salted `bcrypt_pbkdf` with a configurable number of rounds, for deployments that want
+    /// guessing to be expensive rather than instant.
+    Bcrypt {
+        cost: u32,
+    },
+}
+
+impl Default for HashScheme {
+    fn default() -> Self {
+        HashScheme::Md5
+    }
+}
+
+impl HashScheme {
+    /// Number of raw bytes this scheme's digest occupies before base64 encoding.
+    fn digest_bytes(&self) -> usize {
+        match self {
+            HashScheme::Md5 => 16,
+            HashScheme::Sha256 => 32,
+            HashScheme::Bcrypt { .. } => 32,
+        }
+    }
+
+    /// Length a base64-encoded hash produced by this scheme is expected to have.
+    pub fn encoded_len(&self) -> usize {
+        (self.digest_bytes() + 2) / 3 * 4
+    }
+
+    /// Hash `input` salted with `salt`, base64-encoding the result the same way regardless of
+    /// scheme so callers can compare hashes without caring which one produced them.
+    pub fn hash(&self, input: &str, salt: &str) -> String {
+        match self {
+            HashScheme::Md5 => {
+                let mut hasher = Md5::new();
+                hasher.update(input.as_bytes());
+                hasher.update(salt.as_bytes());
+                encode(hasher.finalize())
+            }
+            HashScheme::Sha256 => {
+                let mut hasher = Sha256::new();
+                hasher.update(input.as_bytes());
+                hasher.update(salt.as_bytes());
+                encode(hasher.finalize())
+            }
+            HashScheme::Bcrypt { cost } => {
+                let mut out = [0u8; 32];
+                bcrypt_pbkdf::bcrypt_pbkdf(input.as_bytes(), salt.as_bytes(), *cost, &mut out)
+                    .expect("fixed-size output and non-empty password/salt are always valid");
+                encode(out)
+            }
+        }
+    }
+}
@@ -0,0 +1,205 @@
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex};
+
+use async_trait::async_trait;
+use redis::{AsyncCommands, Client as RedisClient};
+use serde::{Deserialize, Serialize};
+
+use crate::error::AppError;
+
+const LEADERBOARD_KEY: &str = "leaderboard";
+
+/// A single ranked player, as returned from [`LeaderboardStore::top`].
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct LeaderboardEntry {
+    pub player: String,
+    pub guess_count: usize,
+}
+
+/// Tracks the best `guess_count` each player has solved a game in.
+///
+/// Backed by a Redis sorted set keyed on `guess_count` (negated, so fewer guesses sort highest),
+/// following the same store/mock split as [`crate::storage::GameStore`].
+#[async_trait]
+pub trait LeaderboardStore: Send + Sync {
+    /// Record that `player` solved a game in `guess_count` guesses.
+    async fn record_solve(&self, player: &str, guess_count: usize) -> Result<(), AppError>;
+
+    /// The `top` best-ranked players, fewest guesses first.
+    async fn top(&self, top: usize) -> Result<Vec<LeaderboardEntry>, AppError>;
+
+    /// `player`'s 0-based rank (0 = best), or `None` if they haven't solved a game yet.
+    async fn rank(&self, player: &str) -> Result<Option<u64>, AppError>;
+}
+
+fn score_for(guess_count: usize) -> i64 {
+    -(guess_count as i64)
+}
+
+pub struct RedisLeaderboardStore {
+    client: Arc<RedisClient>,
+}
+
+impl RedisLeaderboardStore {
+    pub fn new(client: Arc<RedisClient>) -> Self {
+        Self { client }
+    }
+}
+
+#[async_trait]
+impl LeaderboardStore for RedisLeaderboardStore {
+    async fn record_solve(&self, player: &str, guess_count: usize) -> Result<(), AppError> {
+        let mut conn = self
+            .client
+            .get_async_connection()
+            .await
+            .map_err(|_| AppError::InternalServerError)?;
+        // GT: only raise a player's score, never lower it — a worse later solve must not
+        // downgrade a best-guess-count already on the board.
+        redis::cmd("ZADD")
+            .arg(LEADERBOARD_KEY)
+            .arg("GT")
+            .arg(score_for(guess_count))
+            .arg(player)
+            .query_async(&mut conn)
+            .await
+            .map_err(|_| AppError::InternalServerError)
+    }
+
+    async fn top(&self, top: usize) -> Result<Vec<LeaderboardEntry>, AppError> {
+        if top == 0 {
+            return Ok(Vec::new());
+        }
+        let mut conn = self
+            .client
+            .get_async_connection()
+            .await
+            .map_err(|_| AppError::InternalServerError)?;
+        let entries: Vec<(String, i64)> = conn
+            .zrevrange_withscores(LEADERBOARD_KEY, 0, top.saturating_sub(1) as isize)
+            .await
+            .map_err(|_| AppError::InternalServerError)?;
+        Ok(entries
+            .into_iter()
+            .map(|(player, score)| LeaderboardEntry {
+                player,
+                guess_count: (-score) as usize,
+            })
+            .collect())
+    }
+
+    async fn rank(&self, player: &str) -> Result<Option<u64>, AppError> {
+        let mut conn = self
+            .client
+            .get_async_connection()
+            .await
+            .map_err(|_| AppError::InternalServerError)?;
+        conn.zrevrank(LEADERBOARD_KEY, player)
+            .await
+            .map_err(|_| AppError::InternalServerError)
+    }
+}
+
+/// [`LeaderboardStore`] backed by a plain `HashMap`, for tests.
+#[derive(Default)]
+pub struct InMemoryLeaderboardStore {
+    scores: Mutex<HashMap<String, i64>>,
+}
+
+impl InMemoryLeaderboardStore {
+    pub fn new() -> Self {
+        Self::default()
+    }
+}
+
+#[async_trait]
+impl LeaderboardStore for InMemoryLeaderboardStore {
+    async fn record_solve(&self, player: &str, guess_count: usize) -> Result<(), AppError> {
+        let score = score_for(guess_count);
+        let mut scores = self.scores.lock().unwrap();
+        // Mirror Redis's ZADD GT: only raise a player's score, never lower it.
+        let improves = match scores.get(player) {
+            Some(&existing) => score > existing,
+            None => true,
+        };
+        if improves {
+            scores.insert(player.to_owned(), score);
+        }
+        Ok(())
+    }
+
+    async fn top(&self, top: usize) -> Result<Vec<LeaderboardEntry>, AppError> {
+        let mut entries: Vec<(String, i64)> = self
+            .scores
+            .lock()
+            .unwrap()
+            .iter()
+            .map(|(player, score)| (player.clone(), *score))
+            .collect();
+        entries.sort_by(|a, b| b.1.cmp(&a.1));
+        Ok(entries
+            .into_iter()
+            .take(top)
+            .map(|(player, score)| LeaderboardEntry {
+                player,
+                guess_count: (-score) as usize,
+            })
+            .collect())
+    }
+
+    async fn rank(&self, player: &str) -> Result<Option<u64>, AppError> {
+        let scores = self.scores.lock().unwrap();
+        let player_score = match scores.get(player) {
+            Some(score) => *score,
+            None => return Ok(None),
+        };
+        let rank = scores
+            .values()
+            .filter(|&&score| score > player_score)
+            .count();
+        Ok(Some(rank as u64))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn ranks_fewest_guesses_first() {
+        let store = InMemoryLeaderboardStore::new();
+        store.record_solve("alice", 5).await.unwrap();
+        store.record_solve("bob", 2).await.unwrap();
+        store.record_solve("carol", 8).await.unwrap();
+
+        let top = store.top(10).await.unwrap();
+        let names: Vec<&str> = top.iter().map(|entry| entry.player.as_str()).collect();
+        assert_eq!(names, vec!["bob", "alice", "carol"]);
+
+        assert_eq!(store.rank("bob").await.unwrap(), Some(0));
+        assert_eq!(store.rank("carol").await.unwrap(), Some(2));
+        assert_eq!(store.rank("dave").await.unwrap(), None);
+    }
+
+    #[tokio::test]
+    async fn later_worse_solve_never_downgrades_a_players_best_score() {
+        let store = InMemoryLeaderboardStore::new();
+        store.record_solve("alice", 2).await.unwrap();
+        store.record_solve("alice", 10).await.unwrap();
+        assert_eq!(store.rank("alice").await.unwrap(), Some(0));
+
+        store.record_solve("alice", 1).await.unwrap();
+        let top = store.top(10).await.unwrap();
+        assert_eq!(top[0].player, "alice");
+        assert_eq!(top[0].guess_count, 1);
+    }
+
+    #[tokio::test]
+    async fn top_is_capped_at_requested_count() {
+        let store = InMemoryLeaderboardStore::new();
+        for (player, guesses) in [("a", 1), ("b", 2), ("c", 3)] {
+            store.record_solve(player, guesses).await.unwrap();
+        }
+        assert_eq!(store.top(2).await.unwrap().len(), 2);
+    }
+}
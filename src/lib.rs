@@ -0,0 +1,9 @@
+pub mod auth;
+pub mod config;
+pub mod error;
+pub mod game;
+pub mod hash;
+pub mod leaderboard;
+pub mod player;
+pub mod room;
+pub mod storage;
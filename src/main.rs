@@ -1,8 +1,12 @@
+use std::convert::Infallible;
 use std::sync::Arc;
 
-use axum::extract::{Extension, Path};
+use axum::extract::{Extension, Path, Query};
+use axum::http::StatusCode;
+use axum::response::sse::{Event, KeepAlive, Sse};
 use axum::routing::{get, post};
 use axum::{Json, Router};
+use futures::stream::{Stream, StreamExt};
 // We prefer to keep `main.rs` and `lib.rs` separate as it makes it easier to add extra helper
 // binaries later which share code with the main project. It could save you from a nontrivial
 // refactoring effort in the future.
@@ -14,26 +18,54 @@ use clap::Parser;
 use redis::Client as RedisClient;
 use uuid::Uuid;
 
+use passwordle::auth::{hash_password, issue_token, verify_password_hash, AuthenticatedPlayer};
 use passwordle::config::Config;
 use passwordle::error::AppError;
 use passwordle::game::{
     create_game, get_game_info, make_guess, GameCreationInfo, GameInfo, GuessResult,
 };
+use passwordle::hash::HashScheme;
+use passwordle::leaderboard::{LeaderboardEntry, LeaderboardStore, RedisLeaderboardStore};
+use passwordle::player::{PlayerStore, RedisPlayerStore};
+use passwordle::room::{
+    create_room, join_room, make_room_guess, RedisRoomStore, RoomCreationInfo, RoomGuessResult,
+    RoomStore,
+};
+use passwordle::storage::{GameStore, RedisGameStore};
 
 #[tokio::main]
 async fn main() -> anyhow::Result<()> {
     dotenv::dotenv().ok();
     let config: Config = Config::parse();
-    let client = RedisClient::open(config.redis_url)?;
-    let client = Arc::new(client);
+    let client = Arc::new(RedisClient::open(config.redis_url.clone())?);
+    let store: Arc<dyn GameStore> = Arc::new(RedisGameStore::new(client.clone()));
+    let leaderboard: Arc<dyn LeaderboardStore> =
+        Arc::new(RedisLeaderboardStore::new(client.clone()));
+    let player_store: Arc<dyn PlayerStore> = Arc::new(RedisPlayerStore::new(client.clone()));
+    let room_store: Arc<dyn RoomStore> = Arc::new(RedisRoomStore::new(client));
+    let bind_url = config.bind_url.clone();
+    let config = Arc::new(config);
 
     let app = Router::new()
         .route("/api/games/:id", get(show_game_status))
+        .route("/api/games/:id/stream", get(game_stream))
         .route("/api/guess/:id/", post(guess_post))
         .route("/api/create", post(game_create_post))
-        .layer(Extension(client));
+        .route("/api/leaderboard", get(leaderboard_get))
+        .route("/api/register", post(register_post))
+        .route("/api/login", post(login_post))
+        .route("/api/me/games", get(me_games_get))
+        .route("/api/rooms", post(room_create_post))
+        .route("/api/rooms/:id/join", post(room_join_post))
+        .route("/api/rooms/:id/guess", post(room_guess_post))
+        .route("/api/rooms/:id/stream", get(room_stream))
+        .layer(Extension(store))
+        .layer(Extension(leaderboard))
+        .layer(Extension(player_store))
+        .layer(Extension(room_store))
+        .layer(Extension(config));
 
-    axum::Server::bind(&config.bind_url.parse()?)
+    axum::Server::bind(&bind_url.parse()?)
         .serve(app.into_make_service())
         .await
         .unwrap();
@@ -44,31 +76,223 @@ async fn main() -> anyhow::Result<()> {
 /// Handler for `GET /games/:id`.
 async fn show_game_status(
     Path(game_id): Path<Uuid>,
-    Extension(client): Extension<Arc<RedisClient>>,
+    Extension(store): Extension<Arc<dyn GameStore>>,
 ) -> Result<Json<GameInfo>, AppError> {
-    let info = get_game_info(client, game_id).await?;
+    let info = get_game_info(store, game_id).await?;
     Ok(info.into())
 }
 
-/// Handler for `POST /create/`.
+/// Handler for `GET /games/:id/stream`. Pushes a [`GuessResult`] as each guess lands, so clients
+/// no longer need to poll `show_game_status`.
+async fn game_stream(
+    Path(game_id): Path<Uuid>,
+    Extension(store): Extension<Arc<dyn GameStore>>,
+) -> Result<Sse<impl Stream<Item = Result<Event, Infallible>>>, AppError> {
+    let events = store.subscribe(game_id).await?;
+    let events = events.map(|payload| Ok(Event::default().event("guess").data(payload)));
+    Ok(Sse::new(events).keep_alive(KeepAlive::default()))
+}
+
+#[derive(Debug, serde::Deserialize)]
+#[serde(rename_all = "snake_case")]
+enum HashSchemeChoice {
+    Md5,
+    Sha256,
+    Bcrypt,
+}
+
+impl HashSchemeChoice {
+    fn resolve(self, bcrypt_cost: u32) -> HashScheme {
+        match self {
+            HashSchemeChoice::Md5 => HashScheme::Md5,
+            HashSchemeChoice::Sha256 => HashScheme::Sha256,
+            HashSchemeChoice::Bcrypt => HashScheme::Bcrypt { cost: bcrypt_cost },
+        }
+    }
+}
+
+#[derive(Debug, Default, serde::Deserialize)]
+struct GameCreateRequest {
+    #[serde(default)]
+    scheme: Option<HashSchemeChoice>,
+}
+
+/// Handler for `POST /create/`. Anonymous if called without an `Authorization` header, in which
+/// case the created game is public: anyone may guess on it.
 async fn game_create_post(
-    Extension(client): Extension<Arc<RedisClient>>,
+    Extension(store): Extension<Arc<dyn GameStore>>,
+    Extension(config): Extension<Arc<Config>>,
+    Extension(player_store): Extension<Arc<dyn PlayerStore>>,
+    auth: Option<AuthenticatedPlayer>,
+    payload: Option<Json<GameCreateRequest>>,
 ) -> Result<Json<GameCreationInfo>, AppError> {
-    let info = create_game(client).await?;
+    let scheme = payload
+        .and_then(|Json(request)| request.scheme)
+        .map(|choice| choice.resolve(config.bcrypt_cost))
+        .unwrap_or_default();
+    let owner = auth.map(|AuthenticatedPlayer(player)| player);
+    let info = create_game(store, scheme, owner.clone()).await?;
+    if let Some(owner) = owner {
+        player_store.add_game(&owner, info.id).await?;
+    }
     Ok(info.into())
 }
 
 #[derive(Debug, serde::Deserialize)]
 struct MakeGuessRequest {
     guess: String,
+    #[serde(default)]
+    player: Option<String>,
 }
 
-/// Handler for `POST /games/:id/guess`.
+/// Handler for `POST /guess/:id/`. Private games (created by an authenticated player) only
+/// accept guesses from their owner.
 async fn guess_post(
     Path(game_id): Path<Uuid>,
+    Extension(store): Extension<Arc<dyn GameStore>>,
+    Extension(leaderboard): Extension<Arc<dyn LeaderboardStore>>,
+    auth: Option<AuthenticatedPlayer>,
     Json(payload): Json<MakeGuessRequest>,
-    Extension(client): Extension<Arc<RedisClient>>,
 ) -> Result<Json<GuessResult>, AppError> {
-    let info = make_guess(client, game_id, payload.guess).await?;
+    let requester = auth.map(|AuthenticatedPlayer(player)| player);
+    let info = make_guess(
+        store,
+        leaderboard,
+        game_id,
+        payload.guess,
+        payload.player,
+        requester,
+    )
+    .await?;
+    Ok(info.into())
+}
+
+#[derive(Debug, serde::Deserialize)]
+struct AuthRequest {
+    username: String,
+    password: String,
+}
+
+#[derive(Debug, serde::Serialize)]
+struct TokenResponse {
+    token: String,
+}
+
+/// Handler for `POST /api/register`. Registers a new player and, like `login_post`, returns a
+/// token for them immediately so clients don't need a separate login round-trip.
+async fn register_post(
+    Extension(player_store): Extension<Arc<dyn PlayerStore>>,
+    Extension(config): Extension<Arc<Config>>,
+    Json(payload): Json<AuthRequest>,
+) -> Result<Json<TokenResponse>, AppError> {
+    let password_hash = hash_password(&payload.password, &payload.username, config.bcrypt_cost);
+    player_store
+        .register(&payload.username, &password_hash)
+        .await?;
+    let token = issue_token(&payload.username, config.jwt_secret.as_bytes())?;
+    Ok(Json(TokenResponse { token }))
+}
+
+/// Handler for `POST /api/login`.
+async fn login_post(
+    Extension(player_store): Extension<Arc<dyn PlayerStore>>,
+    Extension(config): Extension<Arc<Config>>,
+    Json(payload): Json<AuthRequest>,
+) -> Result<Json<TokenResponse>, AppError> {
+    let password_hash = hash_password(&payload.password, &payload.username, config.bcrypt_cost);
+    let stored_hash = player_store
+        .password_hash(&payload.username)
+        .await?
+        .ok_or(AppError::Unauthorized)?;
+    if !verify_password_hash(&stored_hash, &password_hash) {
+        return Err(AppError::Unauthorized);
+    }
+    let token = issue_token(&payload.username, config.jwt_secret.as_bytes())?;
+    Ok(Json(TokenResponse { token }))
+}
+
+/// Handler for `GET /api/me/games`.
+async fn me_games_get(
+    AuthenticatedPlayer(player): AuthenticatedPlayer,
+    Extension(player_store): Extension<Arc<dyn PlayerStore>>,
+) -> Result<Json<Vec<Uuid>>, AppError> {
+    let games = player_store.list_games(&player).await?;
+    Ok(Json(games))
+}
+
+#[derive(Debug, Default, serde::Deserialize)]
+struct RoomCreateRequest {
+    #[serde(default)]
+    scheme: Option<HashSchemeChoice>,
+}
+
+/// Handler for `POST /api/rooms`.
+async fn room_create_post(
+    Extension(room_store): Extension<Arc<dyn RoomStore>>,
+    Extension(config): Extension<Arc<Config>>,
+    payload: Option<Json<RoomCreateRequest>>,
+) -> Result<Json<RoomCreationInfo>, AppError> {
+    let scheme = payload
+        .and_then(|Json(request)| request.scheme)
+        .map(|choice| choice.resolve(config.bcrypt_cost))
+        .unwrap_or_default();
+    let info = create_room(room_store, scheme).await?;
     Ok(info.into())
 }
+
+/// Handler for `POST /api/rooms/:id/join`. Requires authentication so a room's membership (and
+/// later `declare_winner` standing) reflects verified identities rather than caller-supplied names.
+async fn room_join_post(
+    Path(room_id): Path<Uuid>,
+    Extension(room_store): Extension<Arc<dyn RoomStore>>,
+    AuthenticatedPlayer(player): AuthenticatedPlayer,
+) -> Result<StatusCode, AppError> {
+    join_room(room_store, room_id, &player).await?;
+    Ok(StatusCode::NO_CONTENT)
+}
+
+#[derive(Debug, serde::Deserialize)]
+struct RoomGuessRequest {
+    guess: String,
+}
+
+/// Handler for `POST /api/rooms/:id/guess`. Requires authentication, same as `room_join_post`.
+async fn room_guess_post(
+    Path(room_id): Path<Uuid>,
+    Extension(room_store): Extension<Arc<dyn RoomStore>>,
+    AuthenticatedPlayer(player): AuthenticatedPlayer,
+    Json(payload): Json<RoomGuessRequest>,
+) -> Result<Json<RoomGuessResult>, AppError> {
+    let result = make_room_guess(room_store, room_id, player, payload.guess).await?;
+    Ok(Json(result))
+}
+
+/// Handler for `GET /api/rooms/:id/stream`. Pushes every player's [`RoomGuessResult`] as it
+/// lands, so clients can render a live shared standings board.
+async fn room_stream(
+    Path(room_id): Path<Uuid>,
+    Extension(room_store): Extension<Arc<dyn RoomStore>>,
+) -> Result<Sse<impl Stream<Item = Result<Event, Infallible>>>, AppError> {
+    let events = room_store.subscribe(room_id).await?;
+    let events = events.map(|payload| Ok(Event::default().event("guess").data(payload)));
+    Ok(Sse::new(events).keep_alive(KeepAlive::default()))
+}
+
+#[derive(Debug, serde::Deserialize)]
+struct LeaderboardQuery {
+    #[serde(default = "default_leaderboard_top")]
+    top: usize,
+}
+
+fn default_leaderboard_top() -> usize {
+    10
+}
+
+/// Handler for `GET /api/leaderboard?top=N`.
+async fn leaderboard_get(
+    Query(query): Query<LeaderboardQuery>,
+    Extension(leaderboard): Extension<Arc<dyn LeaderboardStore>>,
+) -> Result<Json<Vec<LeaderboardEntry>>, AppError> {
+    let entries = leaderboard.top(query.top).await?;
+    Ok(Json(entries))
+}
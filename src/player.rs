@@ -0,0 +1,173 @@
+use std::collections::{HashMap, HashSet};
+use std::sync::{Arc, Mutex};
+
+use async_trait::async_trait;
+use redis::{AsyncCommands, Client as RedisClient};
+use uuid::Uuid;
+
+use crate::error::AppError;
+
+/// Registered player accounts and the games each one owns.
+///
+/// Backed by a `player:{name}:password` string and a `player:{name}:games` set, mirroring the
+/// `game:{id}:*` key layout used by [`crate::storage::GameStore`].
+#[async_trait]
+pub trait PlayerStore: Send + Sync {
+    /// Register a new player with the given (already-hashed) password. Fails with
+    /// [`AppError::BadRequest`] if the username is already taken.
+    async fn register(&self, username: &str, password_hash: &str) -> Result<(), AppError>;
+
+    /// The stored password hash for `username`, or `None` if they haven't registered.
+    async fn password_hash(&self, username: &str) -> Result<Option<String>, AppError>;
+
+    /// Record that `username` owns `game_id`.
+    async fn add_game(&self, username: &str, game_id: Uuid) -> Result<(), AppError>;
+
+    /// All games `username` owns.
+    async fn list_games(&self, username: &str) -> Result<Vec<Uuid>, AppError>;
+}
+
+/// [`PlayerStore`] backed by the real Redis client used in production.
+pub struct RedisPlayerStore {
+    client: Arc<RedisClient>,
+}
+
+impl RedisPlayerStore {
+    pub fn new(client: Arc<RedisClient>) -> Self {
+        Self { client }
+    }
+}
+
+#[async_trait]
+impl PlayerStore for RedisPlayerStore {
+    async fn register(&self, username: &str, password_hash: &str) -> Result<(), AppError> {
+        let mut conn = self
+            .client
+            .get_async_connection()
+            .await
+            .map_err(|_| AppError::InternalServerError)?;
+        let set: bool = redis::cmd("SET")
+            .arg(format!("player:{}:password", username))
+            .arg(password_hash)
+            .arg("NX")
+            .query_async(&mut conn)
+            .await
+            .map_err(|_| AppError::InternalServerError)?;
+        if set {
+            Ok(())
+        } else {
+            Err(AppError::BadRequest)
+        }
+    }
+
+    async fn password_hash(&self, username: &str) -> Result<Option<String>, AppError> {
+        let mut conn = self
+            .client
+            .get_async_connection()
+            .await
+            .map_err(|_| AppError::InternalServerError)?;
+        conn.get(format!("player:{}:password", username))
+            .await
+            .map_err(|_| AppError::InternalServerError)
+    }
+
+    async fn add_game(&self, username: &str, game_id: Uuid) -> Result<(), AppError> {
+        let mut conn = self
+            .client
+            .get_async_connection()
+            .await
+            .map_err(|_| AppError::InternalServerError)?;
+        conn.sadd(format!("player:{}:games", username), game_id.to_string())
+            .await
+            .map_err(|_| AppError::InternalServerError)
+    }
+
+    async fn list_games(&self, username: &str) -> Result<Vec<Uuid>, AppError> {
+        let mut conn = self
+            .client
+            .get_async_connection()
+            .await
+            .map_err(|_| AppError::InternalServerError)?;
+        let raw: Vec<String> = conn
+            .smembers(format!("player:{}:games", username))
+            .await
+            .map_err(|_| AppError::InternalServerError)?;
+        Ok(raw
+            .iter()
+            .filter_map(|id| Uuid::parse_str(id).ok())
+            .collect())
+    }
+}
+
+/// [`PlayerStore`] backed by plain `HashMap`s, for tests.
+#[derive(Default)]
+pub struct InMemoryPlayerStore {
+    passwords: Mutex<HashMap<String, String>>,
+    games: Mutex<HashMap<String, HashSet<Uuid>>>,
+}
+
+impl InMemoryPlayerStore {
+    pub fn new() -> Self {
+        Self::default()
+    }
+}
+
+#[async_trait]
+impl PlayerStore for InMemoryPlayerStore {
+    async fn register(&self, username: &str, password_hash: &str) -> Result<(), AppError> {
+        let mut passwords = self.passwords.lock().unwrap();
+        if passwords.contains_key(username) {
+            return Err(AppError::BadRequest);
+        }
+        passwords.insert(username.to_owned(), password_hash.to_owned());
+        Ok(())
+    }
+
+    async fn password_hash(&self, username: &str) -> Result<Option<String>, AppError> {
+        Ok(self.passwords.lock().unwrap().get(username).cloned())
+    }
+
+    async fn add_game(&self, username: &str, game_id: Uuid) -> Result<(), AppError> {
+        self.games
+            .lock()
+            .unwrap()
+            .entry(username.to_owned())
+            .or_default()
+            .insert(game_id);
+        Ok(())
+    }
+
+    async fn list_games(&self, username: &str) -> Result<Vec<Uuid>, AppError> {
+        Ok(self
+            .games
+            .lock()
+            .unwrap()
+            .get(username)
+            .cloned()
+            .unwrap_or_default()
+            .into_iter()
+            .collect())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn duplicate_registration_is_rejected() {
+        let store = InMemoryPlayerStore::new();
+        store.register("alice", "hash").await.unwrap();
+        let result = store.register("alice", "other-hash").await;
+        assert!(matches!(result, Err(AppError::BadRequest)));
+    }
+
+    #[tokio::test]
+    async fn tracks_games_owned_by_a_player() {
+        let store = InMemoryPlayerStore::new();
+        let game_id = Uuid::new_v4();
+        store.add_game("alice", game_id).await.unwrap();
+        assert_eq!(store.list_games("alice").await.unwrap(), vec![game_id]);
+        assert_eq!(store.list_games("bob").await.unwrap(), Vec::new());
+    }
+}
@@ -0,0 +1,534 @@
+use std::collections::{HashMap, HashSet};
+use std::sync::{Arc, Mutex};
+
+use async_trait::async_trait;
+use rand::{Rng, SeedableRng};
+use redis::{AsyncCommands, Client as RedisClient};
+use serde::{Deserialize, Serialize};
+use tokio::sync::broadcast;
+use tokio_stream::wrappers::BroadcastStream;
+use uuid::Uuid;
+
+use crate::error::AppError;
+use crate::game::{check_guess, Match, PASSWORD_LENGTH};
+use crate::hash::HashScheme;
+use crate::storage::{drain_complete_events, EventStream};
+
+const SALT_LENGTH: usize = 8;
+const ROOM_EXPIRE: usize = 60 * 60 * 24;
+
+/// A room's shared-password state as last loaded from the store.
+#[derive(Debug, Default, Clone)]
+pub struct LoadedRoom {
+    pub salt: Option<String>,
+    pub password: Option<String>,
+    pub scheme: Option<HashScheme>,
+    /// The player who first solved the room's password, once there is one. A room with a
+    /// winner is closed: further guesses return [`AppError::RoomFinished`].
+    pub winner: Option<String>,
+}
+
+/// Backing store for multiplayer rooms, where several players race to guess the same password.
+///
+/// All key layouts mirror the `room:{id}:*` keys used directly against Redis, following the same
+/// store/mock split as [`crate::storage::GameStore`].
+#[async_trait]
+pub trait RoomStore: Send + Sync {
+    /// Create a new room with the given shared `salt`/`password`/`scheme`, expiring after
+    /// `expire` seconds if nobody finishes it first.
+    async fn create(
+        &self,
+        room_id: Uuid,
+        salt: &str,
+        password: &str,
+        scheme: HashScheme,
+        expire: usize,
+    ) -> Result<(), AppError>;
+
+    /// Load the current state of a room.
+    async fn load(&self, room_id: Uuid) -> Result<LoadedRoom, AppError>;
+
+    /// Add `player` to the room's membership set.
+    async fn join(&self, room_id: Uuid, player: &str) -> Result<(), AppError>;
+
+    /// Whether `player` has joined the room.
+    async fn is_member(&self, room_id: Uuid, player: &str) -> Result<bool, AppError>;
+
+    /// Atomically increment and return `player`'s guess count within the room.
+    async fn incr_guess_count(&self, room_id: Uuid, player: &str) -> Result<usize, AppError>;
+
+    /// Atomically declare `player` the winner. Returns `true` if this call won the race, `false`
+    /// if the room already had a winner.
+    async fn declare_winner(&self, room_id: Uuid, player: &str) -> Result<bool, AppError>;
+
+    /// Publish a JSON-encoded event to subscribers of `room_id`, e.g. for the SSE stream.
+    async fn publish(&self, room_id: Uuid, payload: String) -> Result<(), AppError>;
+
+    /// Subscribe to JSON-encoded events published for `room_id`.
+    async fn subscribe(&self, room_id: Uuid) -> Result<EventStream, AppError>;
+}
+
+/// [`RoomStore`] backed by the real Redis client used in production.
+pub struct RedisRoomStore {
+    client: Arc<RedisClient>,
+}
+
+impl RedisRoomStore {
+    pub fn new(client: Arc<RedisClient>) -> Self {
+        Self { client }
+    }
+}
+
+#[async_trait]
+impl RoomStore for RedisRoomStore {
+    async fn create(
+        &self,
+        room_id: Uuid,
+        salt: &str,
+        password: &str,
+        scheme: HashScheme,
+        expire: usize,
+    ) -> Result<(), AppError> {
+        let scheme = serde_json::to_string(&scheme).map_err(|_| AppError::InternalServerError)?;
+        let mut conn = self
+            .client
+            .get_async_connection()
+            .await
+            .map_err(|_| AppError::InternalServerError)?;
+        redis::pipe()
+            .set_ex(format!("room:{}:salt", room_id), salt, expire)
+            .set_ex(format!("room:{}:password", room_id), password, expire)
+            .set_ex(format!("room:{}:scheme", room_id), scheme, expire)
+            .query_async(&mut conn)
+            .await
+            .map_err(|_| AppError::InternalServerError)?;
+        Ok(())
+    }
+
+    async fn load(&self, room_id: Uuid) -> Result<LoadedRoom, AppError> {
+        let mut conn = self
+            .client
+            .get_async_connection()
+            .await
+            .map_err(|_| AppError::InternalServerError)?;
+        let (salt, password, scheme, winner): (
+            Option<String>,
+            Option<String>,
+            Option<String>,
+            Option<String>,
+        ) = redis::pipe()
+            .get(format!("room:{}:salt", room_id))
+            .get(format!("room:{}:password", room_id))
+            .get(format!("room:{}:scheme", room_id))
+            .get(format!("room:{}:winner", room_id))
+            .query_async(&mut conn)
+            .await
+            .map_err(|_| AppError::InternalServerError)?;
+        let scheme = match scheme {
+            Some(scheme) => {
+                Some(serde_json::from_str(&scheme).map_err(|_| AppError::InternalServerError)?)
+            }
+            None => None,
+        };
+        Ok(LoadedRoom {
+            salt,
+            password,
+            scheme,
+            winner,
+        })
+    }
+
+    async fn join(&self, room_id: Uuid, player: &str) -> Result<(), AppError> {
+        let mut conn = self
+            .client
+            .get_async_connection()
+            .await
+            .map_err(|_| AppError::InternalServerError)?;
+        let key = format!("room:{}:members", room_id);
+        redis::pipe()
+            .sadd(&key, player)
+            .ignore()
+            .expire(&key, ROOM_EXPIRE)
+            .ignore()
+            .query_async(&mut conn)
+            .await
+            .map_err(|_| AppError::InternalServerError)
+    }
+
+    async fn is_member(&self, room_id: Uuid, player: &str) -> Result<bool, AppError> {
+        let mut conn = self
+            .client
+            .get_async_connection()
+            .await
+            .map_err(|_| AppError::InternalServerError)?;
+        conn.sismember(format!("room:{}:members", room_id), player)
+            .await
+            .map_err(|_| AppError::InternalServerError)
+    }
+
+    async fn incr_guess_count(&self, room_id: Uuid, player: &str) -> Result<usize, AppError> {
+        let mut conn = self
+            .client
+            .get_async_connection()
+            .await
+            .map_err(|_| AppError::InternalServerError)?;
+        let key = format!("room:{}:player:{}:guess_count", room_id, player);
+        redis::pipe()
+            .incr(&key, 1)
+            .expire(&key, ROOM_EXPIRE)
+            .ignore()
+            .query_async(&mut conn)
+            .await
+            .map_err(|_| AppError::InternalServerError)
+    }
+
+    async fn declare_winner(&self, room_id: Uuid, player: &str) -> Result<bool, AppError> {
+        let mut conn = self
+            .client
+            .get_async_connection()
+            .await
+            .map_err(|_| AppError::InternalServerError)?;
+        redis::cmd("SET")
+            .arg(format!("room:{}:winner", room_id))
+            .arg(player)
+            .arg("NX")
+            .arg("EX")
+            .arg(ROOM_EXPIRE)
+            .query_async(&mut conn)
+            .await
+            .map_err(|_| AppError::InternalServerError)
+    }
+
+    async fn publish(&self, room_id: Uuid, payload: String) -> Result<(), AppError> {
+        let mut conn = self
+            .client
+            .get_async_connection()
+            .await
+            .map_err(|_| AppError::InternalServerError)?;
+        redis::cmd("PUBLISH")
+            .arg(format!("room:{}:events", room_id))
+            .arg(payload)
+            .query_async(&mut conn)
+            .await
+            .map_err(|_| AppError::InternalServerError)
+    }
+
+    async fn subscribe(&self, room_id: Uuid) -> Result<EventStream, AppError> {
+        let mut pubsub = self
+            .client
+            .get_async_connection()
+            .await
+            .map_err(|_| AppError::InternalServerError)?
+            .into_pubsub();
+        pubsub
+            .subscribe(format!("room:{}:events", room_id))
+            .await
+            .map_err(|_| AppError::InternalServerError)?;
+
+        let mut buffer: Vec<u8> = Vec::new();
+        let stream = pubsub.into_on_message().flat_map(move |msg| {
+            let bytes = msg.get_payload_bytes();
+            buffer.extend_from_slice(bytes);
+            let events = drain_complete_events(&mut buffer);
+            futures::stream::iter(events)
+        });
+        Ok(Box::pin(stream))
+    }
+}
+
+#[derive(Default)]
+struct InMemoryRoomRecord {
+    salt: Option<String>,
+    password: Option<String>,
+    scheme: Option<HashScheme>,
+    winner: Option<String>,
+    members: HashSet<String>,
+    guess_counts: HashMap<String, usize>,
+}
+
+/// [`RoomStore`] backed by a plain `HashMap`, for tests.
+#[derive(Default)]
+pub struct InMemoryRoomStore {
+    rooms: Mutex<HashMap<Uuid, InMemoryRoomRecord>>,
+    events: Mutex<HashMap<Uuid, broadcast::Sender<String>>>,
+}
+
+impl InMemoryRoomStore {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    fn events_channel(&self, room_id: Uuid) -> broadcast::Sender<String> {
+        self.events
+            .lock()
+            .unwrap()
+            .entry(room_id)
+            .or_insert_with(|| broadcast::channel(16).0)
+            .clone()
+    }
+}
+
+#[async_trait]
+impl RoomStore for InMemoryRoomStore {
+    async fn create(
+        &self,
+        room_id: Uuid,
+        salt: &str,
+        password: &str,
+        scheme: HashScheme,
+        _expire: usize,
+    ) -> Result<(), AppError> {
+        self.rooms.lock().unwrap().insert(
+            room_id,
+            InMemoryRoomRecord {
+                salt: Some(salt.to_owned()),
+                password: Some(password.to_owned()),
+                scheme: Some(scheme),
+                ..Default::default()
+            },
+        );
+        Ok(())
+    }
+
+    async fn load(&self, room_id: Uuid) -> Result<LoadedRoom, AppError> {
+        let rooms = self.rooms.lock().unwrap();
+        match rooms.get(&room_id) {
+            Some(record) => Ok(LoadedRoom {
+                salt: record.salt.clone(),
+                password: record.password.clone(),
+                scheme: record.scheme,
+                winner: record.winner.clone(),
+            }),
+            None => Ok(LoadedRoom::default()),
+        }
+    }
+
+    async fn join(&self, room_id: Uuid, player: &str) -> Result<(), AppError> {
+        if let Some(record) = self.rooms.lock().unwrap().get_mut(&room_id) {
+            record.members.insert(player.to_owned());
+        }
+        Ok(())
+    }
+
+    async fn is_member(&self, room_id: Uuid, player: &str) -> Result<bool, AppError> {
+        Ok(self
+            .rooms
+            .lock()
+            .unwrap()
+            .get(&room_id)
+            .map(|record| record.members.contains(player))
+            .unwrap_or(false))
+    }
+
+    async fn incr_guess_count(&self, room_id: Uuid, player: &str) -> Result<usize, AppError> {
+        let mut rooms = self.rooms.lock().unwrap();
+        let record = rooms.entry(room_id).or_default();
+        let count = record.guess_counts.entry(player.to_owned()).or_insert(0);
+        *count += 1;
+        Ok(*count)
+    }
+
+    async fn declare_winner(&self, room_id: Uuid, player: &str) -> Result<bool, AppError> {
+        let mut rooms = self.rooms.lock().unwrap();
+        let record = rooms.entry(room_id).or_default();
+        if record.winner.is_some() {
+            Ok(false)
+        } else {
+            record.winner = Some(player.to_owned());
+            Ok(true)
+        }
+    }
+
+    async fn publish(&self, room_id: Uuid, payload: String) -> Result<(), AppError> {
+        let _ = self.events_channel(room_id).send(payload);
+        Ok(())
+    }
+
+    async fn subscribe(&self, room_id: Uuid) -> Result<EventStream, AppError> {
+        let receiver = self.events_channel(room_id).subscribe();
+        let stream = BroadcastStream::new(receiver).filter_map(|event| async { event.ok() });
+        Ok(Box::pin(stream))
+    }
+}
+
+/// Info returned when a room is created, so the host can share the room with other players.
+#[derive(Serialize, Deserialize, Debug)]
+pub struct RoomCreationInfo {
+    pub salt: String,
+    pub id: Uuid,
+    pub scheme: HashScheme,
+}
+
+/// Generate a fresh shared password and register a new room for it.
+pub async fn create_room(
+    store: Arc<dyn RoomStore>,
+    scheme: HashScheme,
+) -> Result<RoomCreationInfo, AppError> {
+    let mut rng = rand::rngs::StdRng::from_entropy();
+    let salt: String = String::from_utf8(
+        (0..SALT_LENGTH)
+            .map(|_| rng.sample(&rand::distributions::Alphanumeric))
+            .collect(),
+    )
+    .unwrap();
+    let password: String = String::from_utf8(
+        (0..PASSWORD_LENGTH)
+            .map(|_| rng.sample(&rand::distributions::Alphanumeric))
+            .collect(),
+    )
+    .unwrap();
+    let password = scheme.hash(&password, &salt);
+    let room_id = Uuid::from_bytes(rng.gen());
+
+    store
+        .create(room_id, &salt, &password, scheme, ROOM_EXPIRE)
+        .await?;
+
+    Ok(RoomCreationInfo {
+        salt,
+        id: room_id,
+        scheme,
+    })
+}
+
+/// Add `player` to a room's membership, so they can start guessing.
+pub async fn join_room(
+    store: Arc<dyn RoomStore>,
+    room_id: Uuid,
+    player: &str,
+) -> Result<(), AppError> {
+    let loaded = store.load(room_id).await?;
+    if loaded.password.is_none() {
+        return Err(AppError::GameNotFound);
+    }
+    store.join(room_id, player).await
+}
+
+/// A single player's guess within a room, broadcast to every member so everyone can see live
+/// standings.
+#[derive(Serialize, Deserialize, Debug)]
+pub struct RoomGuessResult {
+    pub player: String,
+    pub hash: String,
+    pub guess: Vec<Match>,
+    pub key: Option<String>,
+    pub guess_count: usize,
+    /// Whether this guess is the one that won the room.
+    pub winner: bool,
+}
+
+pub async fn make_room_guess(
+    store: Arc<dyn RoomStore>,
+    room_id: Uuid,
+    player: String,
+    guess: String,
+) -> Result<RoomGuessResult, AppError> {
+    if guess.len() != PASSWORD_LENGTH {
+        return Err(AppError::BadRequest);
+    }
+    let loaded = store.load(room_id).await?;
+    let (salt, password) = match (loaded.salt, loaded.password) {
+        (Some(salt), Some(password)) => (salt, password),
+        _ => return Err(AppError::GameNotFound),
+    };
+    if loaded.winner.is_some() {
+        return Err(AppError::RoomFinished);
+    }
+    if !store.is_member(room_id, &player).await? {
+        return Err(AppError::Unauthorized);
+    }
+
+    let scheme = loaded.scheme.unwrap_or_default();
+    let guess_count = store.incr_guess_count(room_id, &player).await?;
+    let hashed = scheme.hash(&guess, &salt);
+    if hashed.len() != password.len() {
+        return Err(AppError::InternalServerError);
+    }
+    let checked = check_guess(hashed, password);
+    let winner = checked.key.is_some() && store.declare_winner(room_id, &player).await?;
+
+    let result = RoomGuessResult {
+        player,
+        hash: checked.hash,
+        guess: checked.guess,
+        key: checked.key,
+        guess_count,
+        winner,
+    };
+    if let Ok(event) = serde_json::to_string(&result) {
+        let _ = store.publish(room_id, event).await;
+    }
+    Ok(result)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn first_player_to_guess_all_exact_wins_and_closes_the_room() {
+        let store: Arc<dyn RoomStore> = Arc::new(InMemoryRoomStore::new());
+        let room_id = Uuid::new_v4();
+        store
+            .create(
+                room_id,
+                "saltsalt",
+                &HashScheme::Md5.hash("swordfsh", "saltsalt"),
+                HashScheme::Md5,
+                ROOM_EXPIRE,
+            )
+            .await
+            .unwrap();
+        store.join(room_id, "alice").await.unwrap();
+        store.join(room_id, "bob").await.unwrap();
+
+        let win = make_room_guess(store.clone(), room_id, "alice".into(), "swordfsh".into())
+            .await
+            .unwrap();
+        assert!(win.winner);
+        assert!(win.guess.iter().all(|m| *m == Match::Exact));
+
+        let late = make_room_guess(store, room_id, "bob".into(), "swordfsh".into()).await;
+        assert!(matches!(late, Err(AppError::RoomFinished)));
+    }
+
+    #[tokio::test]
+    async fn scheme_mismatched_with_stored_password_is_rejected() {
+        let store = InMemoryRoomStore::new();
+        let room_id = Uuid::new_v4();
+        // `password` was hashed with Bcrypt, but `scheme` has since expired and defaulted back
+        // to Md5, so a correctly-hashed guess can never match its length.
+        store.rooms.lock().unwrap().insert(
+            room_id,
+            InMemoryRoomRecord {
+                salt: Some("saltsalt".into()),
+                password: Some(HashScheme::Bcrypt { cost: 4 }.hash("swordfsh", "saltsalt")),
+                ..Default::default()
+            },
+        );
+        store.join(room_id, "alice").await.unwrap();
+        let store: Arc<dyn RoomStore> = Arc::new(store);
+
+        let result = make_room_guess(store, room_id, "alice".into(), "xxxxxxxx".into()).await;
+        assert!(matches!(result, Err(AppError::InternalServerError)));
+    }
+
+    #[tokio::test]
+    async fn non_members_cannot_guess() {
+        let store: Arc<dyn RoomStore> = Arc::new(InMemoryRoomStore::new());
+        let room_id = Uuid::new_v4();
+        store
+            .create(
+                room_id,
+                "saltsalt",
+                &HashScheme::Md5.hash("swordfsh", "saltsalt"),
+                HashScheme::Md5,
+                ROOM_EXPIRE,
+            )
+            .await
+            .unwrap();
+
+        let result = make_room_guess(store, room_id, "eve".into(), "xxxxxxxx".into()).await;
+        assert!(matches!(result, Err(AppError::Unauthorized)));
+    }
+}
@@ -0,0 +1,448 @@
+use std::collections::HashMap;
+use std::pin::Pin;
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant, SystemTime, UNIX_EPOCH};
+
+use async_trait::async_trait;
+use futures::stream::{Stream, StreamExt};
+use redis::Client as RedisClient;
+use tokio::sync::broadcast;
+use tokio_stream::wrappers::BroadcastStream;
+use uuid::Uuid;
+
+use crate::error::AppError;
+use crate::hash::HashScheme;
+
+/// A boxed stream of already-decoded game event payloads (JSON text).
+pub type EventStream = Pin<Box<dyn Stream<Item = String> + Send>>;
+
+/// A game's state as last loaded from the store, with each field missing independently if it
+/// was never set, expired, or the backing record is otherwise corrupt.
+#[derive(Debug, Default, Clone)]
+pub struct LoadedGame {
+    pub guess_count: Option<usize>,
+    pub salt: Option<String>,
+    pub password: Option<String>,
+    /// Defaults to [`HashScheme::Md5`] by callers for games created before schemes were tracked.
+    pub scheme: Option<HashScheme>,
+    /// Unix timestamp, in seconds, of when the game was created.
+    pub created_at: Option<u64>,
+    /// The player who created this game, if any. Games with no owner are public: anyone may
+    /// guess. Games with an owner may only be guessed on by that player.
+    pub owner: Option<String>,
+}
+
+/// Backing store for game state, abstracted so `game.rs` can be exercised without a live Redis.
+///
+/// All key layouts mirror the original `game:{id}:*` keys used directly against Redis, so the
+/// two implementations below stay interchangeable from the handlers' point of view.
+#[async_trait]
+pub trait GameStore: Send + Sync {
+    /// Create a new game record with the given `salt`/`password`/`scheme`, expiring after
+    /// `expire` seconds. Stamps the record with the current time for later elapsed-time lookups.
+    /// `owner` is `Some` for a game created by an authenticated player, restricting guesses to
+    /// them; `None` leaves the game public.
+    async fn create(
+        &self,
+        game_id: Uuid,
+        salt: &str,
+        password: &str,
+        scheme: HashScheme,
+        owner: Option<&str>,
+        expire: usize,
+    ) -> Result<(), AppError>;
+
+    /// Load the current state of a game.
+    async fn load(&self, game_id: Uuid) -> Result<LoadedGame, AppError>;
+
+    /// Atomically increment and return the new guess count, or `None` if the game doesn't exist.
+    async fn incr_guess_count(&self, game_id: Uuid) -> Result<Option<usize>, AppError>;
+
+    /// Remove all state associated with a game.
+    async fn delete(&self, game_id: Uuid) -> Result<(), AppError>;
+
+    /// Publish a JSON-encoded event to subscribers of `game_id`, e.g. for the SSE stream.
+    /// Best-effort: a game with no current subscribers simply drops the event.
+    async fn publish(&self, game_id: Uuid, payload: String) -> Result<(), AppError>;
+
+    /// Subscribe to JSON-encoded events published for `game_id`.
+    async fn subscribe(&self, game_id: Uuid) -> Result<EventStream, AppError>;
+}
+
+/// [`GameStore`] backed by the real Redis client used in production.
+pub struct RedisGameStore {
+    client: Arc<RedisClient>,
+}
+
+impl RedisGameStore {
+    pub fn new(client: Arc<RedisClient>) -> Self {
+        Self { client }
+    }
+}
+
+#[async_trait]
+impl GameStore for RedisGameStore {
+    async fn create(
+        &self,
+        game_id: Uuid,
+        salt: &str,
+        password: &str,
+        scheme: HashScheme,
+        owner: Option<&str>,
+        expire: usize,
+    ) -> Result<(), AppError> {
+        let scheme = serde_json::to_string(&scheme).map_err(|_| AppError::InternalServerError)?;
+        let created_at = unix_timestamp();
+        let mut conn = self
+            .client
+            .get_async_connection()
+            .await
+            .map_err(|_| AppError::InternalServerError)?;
+        let mut pipe = redis::pipe();
+        pipe.set_ex(format!("game:{}:guess_count", game_id), 0usize, expire)
+            .set_ex(format!("game:{}:salt", game_id), salt, expire)
+            .set_ex(format!("game:{}:password", game_id), password, expire)
+            .set_ex(format!("game:{}:scheme", game_id), scheme, expire)
+            .set_ex(format!("game:{}:created_at", game_id), created_at, expire);
+        if let Some(owner) = owner {
+            pipe.set_ex(format!("game:{}:owner", game_id), owner, expire);
+        }
+        pipe.query_async(&mut conn)
+            .await
+            .map_err(|_| AppError::InternalServerError)?;
+        Ok(())
+    }
+
+    async fn load(&self, game_id: Uuid) -> Result<LoadedGame, AppError> {
+        let mut conn = self
+            .client
+            .get_async_connection()
+            .await
+            .map_err(|_| AppError::InternalServerError)?;
+        let (guess_count, salt, password, scheme, created_at, owner): (
+            Option<usize>,
+            Option<String>,
+            Option<String>,
+            Option<String>,
+            Option<u64>,
+            Option<String>,
+        ) = redis::pipe()
+            .get(format!("game:{}:guess_count", game_id))
+            .get(format!("game:{}:salt", game_id))
+            .get(format!("game:{}:password", game_id))
+            .get(format!("game:{}:scheme", game_id))
+            .get(format!("game:{}:created_at", game_id))
+            .get(format!("game:{}:owner", game_id))
+            .query_async(&mut conn)
+            .await
+            .map_err(|_| AppError::InternalServerError)?;
+        let scheme = match scheme {
+            Some(scheme) => {
+                Some(serde_json::from_str(&scheme).map_err(|_| AppError::InternalServerError)?)
+            }
+            None => None,
+        };
+        Ok(LoadedGame {
+            guess_count,
+            salt,
+            password,
+            scheme,
+            created_at,
+            owner,
+        })
+    }
+
+    async fn incr_guess_count(&self, game_id: Uuid) -> Result<Option<usize>, AppError> {
+        let mut conn = self
+            .client
+            .get_async_connection()
+            .await
+            .map_err(|_| AppError::InternalServerError)?;
+        // A bare INCR would auto-vivify a TTL-less counter for an expired/made-up game id, so
+        // only increment it if the game record is still actually live.
+        redis::Script::new(
+            r#"
+            if redis.call("EXISTS", KEYS[1]) == 1 then
+                return redis.call("INCR", KEYS[1])
+            else
+                return false
+            end
+            "#,
+        )
+        .key(format!("game:{}:guess_count", game_id))
+        .invoke_async(&mut conn)
+        .await
+        .map_err(|_| AppError::InternalServerError)
+    }
+
+    async fn delete(&self, game_id: Uuid) -> Result<(), AppError> {
+        let mut conn = self
+            .client
+            .get_async_connection()
+            .await
+            .map_err(|_| AppError::InternalServerError)?;
+        redis::pipe()
+            .del(format!("game:{}:guess_count", game_id))
+            .del(format!("game:{}:salt", game_id))
+            .del(format!("game:{}:password", game_id))
+            .del(format!("game:{}:scheme", game_id))
+            .del(format!("game:{}:created_at", game_id))
+            .del(format!("game:{}:owner", game_id))
+            .query_async(&mut conn)
+            .await
+            .map_err(|_| AppError::InternalServerError)?;
+        Ok(())
+    }
+
+    async fn publish(&self, game_id: Uuid, payload: String) -> Result<(), AppError> {
+        let mut conn = self
+            .client
+            .get_async_connection()
+            .await
+            .map_err(|_| AppError::InternalServerError)?;
+        redis::cmd("PUBLISH")
+            .arg(format!("game:{}:events", game_id))
+            .arg(payload)
+            .query_async(&mut conn)
+            .await
+            .map_err(|_| AppError::InternalServerError)
+    }
+
+    async fn subscribe(&self, game_id: Uuid) -> Result<EventStream, AppError> {
+        let mut pubsub = self
+            .client
+            .get_async_connection()
+            .await
+            .map_err(|_| AppError::InternalServerError)?
+            .into_pubsub();
+        pubsub
+            .subscribe(format!("game:{}:events", game_id))
+            .await
+            .map_err(|_| AppError::InternalServerError)?;
+
+        // Redis pub/sub messages arrive as discrete frames, but we decode defensively anyway:
+        // accumulate bytes across messages and only emit once a full JSON value parses, so a
+        // message split across frames (or briefly invalid UTF-8) never produces a bad event.
+        let mut buffer: Vec<u8> = Vec::new();
+        let stream = pubsub.into_on_message().flat_map(move |msg| {
+            let bytes = msg.get_payload_bytes();
+            buffer.extend_from_slice(bytes);
+            let events = drain_complete_events(&mut buffer);
+            futures::stream::iter(events)
+        });
+        Ok(Box::pin(stream))
+    }
+}
+
+/// Pull every complete JSON value currently sitting in `buffer`, leaving any trailing partial
+/// value (or partial UTF-8 sequence) in place for the next call to complete.
+///
+/// Shared with [`crate::room`], which subscribes to its own per-room pub/sub channel but needs
+/// the same defensive buffering.
+pub(crate) fn drain_complete_events(buffer: &mut Vec<u8>) -> Vec<String> {
+    let mut events = Vec::new();
+    loop {
+        let valid = match std::str::from_utf8(buffer) {
+            Ok(text) => text.len(),
+            Err(e) => e.valid_up_to(),
+        };
+        let text = std::str::from_utf8(&buffer[..valid]).expect("prefix is valid UTF-8");
+        if text.is_empty() {
+            break;
+        }
+        let mut parser = serde_json::Deserializer::from_str(text).into_iter::<serde_json::Value>();
+        match parser.next() {
+            Some(Ok(value)) => {
+                let consumed = parser.byte_offset();
+                events.push(value.to_string());
+                buffer.drain(..consumed);
+            }
+            Some(Err(e)) if e.is_eof() => break,
+            Some(Err(_)) => {
+                // Not recoverable: drop the corrupt bytes rather than wedging the stream forever.
+                buffer.clear();
+                break;
+            }
+            None => break,
+        }
+    }
+    events
+}
+
+fn unix_timestamp() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .expect("system clock is after the Unix epoch")
+        .as_secs()
+}
+
+struct InMemoryRecord {
+    guess_count: Option<usize>,
+    salt: Option<String>,
+    password: Option<String>,
+    scheme: Option<HashScheme>,
+    created_at: Option<u64>,
+    owner: Option<String>,
+    expires_at: Instant,
+}
+
+/// [`GameStore`] backed by a plain `HashMap`, with TTL simulated via wall-clock deadlines.
+///
+/// Intended for tests: it lets game-flow logic in `game.rs` be exercised deterministically and
+/// without network I/O, including against partial/corrupt state that would be awkward to set up
+/// against a real Redis instance.
+#[derive(Default)]
+pub struct InMemoryGameStore {
+    games: Mutex<HashMap<Uuid, InMemoryRecord>>,
+    events: Mutex<HashMap<Uuid, broadcast::Sender<String>>>,
+}
+
+impl InMemoryGameStore {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    fn events_channel(&self, game_id: Uuid) -> broadcast::Sender<String> {
+        self.events
+            .lock()
+            .unwrap()
+            .entry(game_id)
+            .or_insert_with(|| broadcast::channel(16).0)
+            .clone()
+    }
+
+    /// Insert a record directly, bypassing `create`, so tests can build partial/corrupt state
+    /// (e.g. a salt with no password) that would never arise from the normal creation path.
+    #[cfg(test)]
+    #[allow(clippy::too_many_arguments)]
+    pub fn insert_raw(
+        &self,
+        game_id: Uuid,
+        guess_count: Option<usize>,
+        salt: Option<String>,
+        password: Option<String>,
+        scheme: Option<HashScheme>,
+        owner: Option<String>,
+        expire: usize,
+    ) {
+        self.games.lock().unwrap().insert(
+            game_id,
+            InMemoryRecord {
+                guess_count,
+                salt,
+                password,
+                scheme,
+                created_at: Some(unix_timestamp()),
+                owner,
+                expires_at: Instant::now() + Duration::from_secs(expire as u64),
+            },
+        );
+    }
+
+    fn is_live(record: &InMemoryRecord) -> bool {
+        record.expires_at > Instant::now()
+    }
+}
+
+#[async_trait]
+impl GameStore for InMemoryGameStore {
+    async fn create(
+        &self,
+        game_id: Uuid,
+        salt: &str,
+        password: &str,
+        scheme: HashScheme,
+        owner: Option<&str>,
+        expire: usize,
+    ) -> Result<(), AppError> {
+        self.games.lock().unwrap().insert(
+            game_id,
+            InMemoryRecord {
+                guess_count: Some(0),
+                salt: Some(salt.to_owned()),
+                password: Some(password.to_owned()),
+                scheme: Some(scheme),
+                created_at: Some(unix_timestamp()),
+                owner: owner.map(|owner| owner.to_owned()),
+                expires_at: Instant::now() + Duration::from_secs(expire as u64),
+            },
+        );
+        Ok(())
+    }
+
+    async fn load(&self, game_id: Uuid) -> Result<LoadedGame, AppError> {
+        let games = self.games.lock().unwrap();
+        match games.get(&game_id) {
+            Some(record) if Self::is_live(record) => Ok(LoadedGame {
+                guess_count: record.guess_count,
+                salt: record.salt.clone(),
+                password: record.password.clone(),
+                scheme: record.scheme,
+                created_at: record.created_at,
+                owner: record.owner.clone(),
+            }),
+            _ => Ok(LoadedGame::default()),
+        }
+    }
+
+    async fn incr_guess_count(&self, game_id: Uuid) -> Result<Option<usize>, AppError> {
+        let mut games = self.games.lock().unwrap();
+        match games.get_mut(&game_id) {
+            Some(record) if Self::is_live(record) => {
+                let next = record.guess_count.unwrap_or(0) + 1;
+                record.guess_count = Some(next);
+                Ok(Some(next))
+            }
+            _ => Ok(None),
+        }
+    }
+
+    async fn delete(&self, game_id: Uuid) -> Result<(), AppError> {
+        self.games.lock().unwrap().remove(&game_id);
+        Ok(())
+    }
+
+    async fn publish(&self, game_id: Uuid, payload: String) -> Result<(), AppError> {
+        // No subscribers is not an error: `send` only fails when the channel has no receivers.
+        let _ = self.events_channel(game_id).send(payload);
+        Ok(())
+    }
+
+    async fn subscribe(&self, game_id: Uuid) -> Result<EventStream, AppError> {
+        let receiver = self.events_channel(game_id).subscribe();
+        let stream = BroadcastStream::new(receiver).filter_map(|event| async { event.ok() });
+        Ok(Box::pin(stream))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn buffers_a_json_value_split_mid_value_across_two_messages() {
+        let mut buffer = Vec::new();
+        buffer.extend_from_slice(br#"{"hash":"a"#);
+        assert!(drain_complete_events(&mut buffer).is_empty());
+
+        buffer.extend_from_slice(br#"bc"}"#);
+        let events = drain_complete_events(&mut buffer);
+        assert_eq!(events, vec![r#"{"hash":"abc"}"#.to_string()]);
+        assert!(buffer.is_empty());
+    }
+
+    #[test]
+    fn buffers_a_multi_byte_utf8_sequence_split_across_two_messages() {
+        let full = r#"{"hash":"é"}"#.as_bytes();
+        // Split inside the two-byte encoding of 'é' so the first call sees invalid UTF-8.
+        let split_at = full.iter().position(|&b| b == 0xC3).unwrap() + 1;
+        let mut buffer = Vec::new();
+        buffer.extend_from_slice(&full[..split_at]);
+        assert!(drain_complete_events(&mut buffer).is_empty());
+
+        buffer.extend_from_slice(&full[split_at..]);
+        let events = drain_complete_events(&mut buffer);
+        assert_eq!(events, vec![r#"{"hash":"é"}"#.to_string()]);
+        assert!(buffer.is_empty());
+    }
+}